@@ -1,15 +1,31 @@
 use std::cmp::min;
-use std::collections::BTreeMap;
-use std::iter::FromIterator;
+use std::collections::{btree_map, BTreeMap, HashMap};
+use std::fmt;
+use std::iter::{FromIterator, Peekable};
 use std::marker::PhantomData;
+use std::num::ParseIntError;
+use std::str::FromStr;
 
 use fedimint_api::encoding::{Decodable, DecodeError, Encodable};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::module::registry::ModuleDecoderRegistry;
 use crate::tiered::InvalidAmountTierError;
 use crate::{Amount, Tiered};
 
+/// Returned by the `checked_*` arithmetic on [`TieredMulti`] instead of silently wrapping.
+#[derive(Debug, Error, Eq, PartialEq)]
+#[error("operation would overflow the total amount")]
+pub struct OverflowError;
+
+/// Returned by [`TieredMulti::checked_sub`] when a tier would be reduced below zero notes.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum TieredMultiSubError {
+    #[error("tier {0} does not have enough notes to subtract")]
+    InsufficientNotes(Amount),
+}
+
 /// Represents coins of different denominations.
 ///
 /// **Attention:** care has to be taken when constructing this to avoid overflow when calculating
@@ -32,6 +48,20 @@ impl<T> TieredMulti<T> {
         Amount { msats: milli_sat }
     }
 
+    /// Same as [`Self::total_amount`] but returns an error instead of silently wrapping if
+    /// summing `tier.msats * count` across all tiers overflows `u64`.
+    pub fn checked_total_amount(&self) -> Result<Amount, OverflowError> {
+        let mut msats: u64 = 0;
+        for (tier, coins) in self.0.iter() {
+            let tier_total = tier
+                .msats
+                .checked_mul(coins.len() as u64)
+                .ok_or(OverflowError)?;
+            msats = msats.checked_add(tier_total).ok_or(OverflowError)?;
+        }
+        Ok(Amount { msats })
+    }
+
     pub fn item_count(&self) -> usize {
         self.0.values().map(|coins| coins.len()).sum()
     }
@@ -100,6 +130,31 @@ impl<T> TieredMulti<T> {
         self.0.values().map(|notes| notes.len()).max().unwrap_or(0)
     }
 
+    /// Number of notes held at each tier, e.g. for reporting denomination statistics.
+    pub fn counts_by_tier(&self) -> Tiered<usize> {
+        self.0
+            .iter()
+            .map(|(tier, notes)| (*tier, notes.len()))
+            .collect()
+    }
+
+    /// Folds the notes within each tier into a single value.
+    pub fn fold_tiers<B>(
+        self,
+        init: impl Fn(Amount) -> B,
+        f: impl Fn(Amount, B, T) -> B,
+    ) -> Tiered<B> {
+        self.0
+            .into_iter()
+            .map(|(tier, notes)| {
+                let acc = notes
+                    .into_iter()
+                    .fold(init(tier), |acc, note| f(tier, acc, note));
+                (tier, acc)
+            })
+            .collect()
+    }
+
     pub fn check_tiers<K>(&self, keys: &Tiered<K>) -> Result<(), InvalidAmountTierError> {
         match self.0.keys().find(|&amt| keys.get(*amt).is_none()) {
             Some(amt) => Err(InvalidAmountTierError(*amt)),
@@ -117,6 +172,28 @@ impl<T> TieredMulti<T> {
     }
 }
 
+impl<T> TieredMulti<T>
+where
+    T: Ord + Clone,
+{
+    /// The smallest note (by `Ord`) at each tier, e.g. for deterministically picking a note to
+    /// spend from each denomination.
+    pub fn min_item_by_tier(&self) -> Tiered<T> {
+        self.0
+            .iter()
+            .filter_map(|(tier, notes)| notes.iter().min().map(|note| (*tier, note.clone())))
+            .collect()
+    }
+
+    /// The largest note (by `Ord`) at each tier.
+    pub fn max_item_by_tier(&self) -> Tiered<T> {
+        self.0
+            .iter()
+            .filter_map(|(tier, notes)| notes.iter().max().map(|note| (*tier, note.clone())))
+            .collect()
+    }
+}
+
 impl<C> TieredMulti<C>
 where
     C: Clone,
@@ -149,6 +226,149 @@ where
 
         Some(coins)
     }
+
+    /// Select notes summing to *exactly* `amount`, using as few notes as possible. Falls back
+    /// to [`Self::select_coins`]'s smallest-sum-≥-`amount` behavior if no exact subset exists.
+    pub fn select_coins_exact(&self, amount: Amount) -> Option<TieredMulti<C>> {
+        if amount > self.total_amount() {
+            return None;
+        }
+        if amount.msats == 0 {
+            return Some(TieredMulti::default());
+        }
+
+        let tiers: Vec<(Amount, usize)> = self
+            .0
+            .iter()
+            .rev()
+            .map(|(tier, notes)| (*tier, notes.len()))
+            .collect();
+
+        // suffix_value[i] is the maximum amount obtainable from tiers[i..], used to prune
+        // branches that can never reach the remaining amount.
+        let mut suffix_value = vec![0u64; tiers.len() + 1];
+        for i in (0..tiers.len()).rev() {
+            let (tier, available) = tiers[i];
+            suffix_value[i] = suffix_value[i + 1] + tier.msats * available as u64;
+        }
+
+        let mut memo = HashMap::new();
+        let counts = select_coins_exact_search(&tiers, &suffix_value, 0, amount.msats, &mut memo);
+
+        match counts {
+            Some(counts) => {
+                let mut map = BTreeMap::new();
+                for ((tier, _), count) in tiers.iter().zip(counts) {
+                    if count > 0 {
+                        map.insert(*tier, self.0[tier][..count].to_vec());
+                    }
+                }
+                Some(TieredMulti(map))
+            }
+            None => self.select_coins(amount),
+        }
+    }
+
+    /// Merges `self` and `other`, concatenating the notes held at each tier.
+    ///
+    /// Returns an [`OverflowError`] instead of wrapping if the combined total amount would
+    /// overflow, mirroring the overflow hazard flagged on [`TieredMulti`]'s doc comment.
+    pub fn checked_add(&self, other: &TieredMulti<C>) -> Result<TieredMulti<C>, OverflowError> {
+        let mut map = self.0.clone();
+        for (tier, notes) in other.0.iter() {
+            map.entry(*tier).or_default().extend(notes.iter().cloned());
+        }
+
+        let merged = TieredMulti(map);
+        merged.checked_total_amount()?;
+        Ok(merged)
+    }
+
+    /// Removes `other`'s notes from `self`, tier by tier.
+    ///
+    /// Returns a [`TieredMultiSubError`] if any tier in `other` has more notes than `self`
+    /// holds at that tier. Tiers emptied by the subtraction are pruned so the invariant that
+    /// no tier's `Vec` is empty (see the `get_mut` TODO above) is preserved.
+    pub fn checked_sub(
+        &self,
+        other: &TieredMulti<C>,
+    ) -> Result<TieredMulti<C>, TieredMultiSubError> {
+        let mut map = self.0.clone();
+        for (tier, notes) in other.0.iter() {
+            let remaining = map
+                .get_mut(tier)
+                .filter(|remaining| remaining.len() >= notes.len())
+                .ok_or(TieredMultiSubError::InsufficientNotes(*tier))?;
+
+            // Drop from the front to match the selection convention used elsewhere in this
+            // file (`select_coins`, `select_coins_exact`), which keeps/takes a tier's leading
+            // notes first.
+            remaining.drain(..notes.len());
+            if remaining.is_empty() {
+                map.remove(tier);
+            }
+        }
+
+        Ok(TieredMulti(map))
+    }
+}
+
+/// Depth-first, memoized branch-and-bound search for [`TieredMulti::select_coins_exact`].
+///
+/// `tiers` is sorted descending by `Amount`. Returns the note count to take from each tier in
+/// `tiers[idx..]` (same length/order, left-padded conceptually by the caller) such that the
+/// selected notes' value sums to exactly `remaining`, minimizing the total number of notes used.
+fn select_coins_exact_search(
+    tiers: &[(Amount, usize)],
+    suffix_value: &[u64],
+    idx: usize,
+    remaining: u64,
+    memo: &mut HashMap<(usize, u64), Option<Vec<usize>>>,
+) -> Option<Vec<usize>> {
+    if remaining == 0 {
+        return Some(vec![0; tiers.len() - idx]);
+    }
+    if idx == tiers.len() || remaining > suffix_value[idx] {
+        return None;
+    }
+    if let Some(cached) = memo.get(&(idx, remaining)) {
+        return cached.clone();
+    }
+
+    let (tier, available) = tiers[idx];
+    let max_count = min(available as u64, remaining / tier.msats) as usize;
+
+    let mut best: Option<(usize, Vec<usize>)> = None;
+    for count in 0..=max_count {
+        // Taking `count` notes from this tier already costs at least `count` notes, so once
+        // that alone matches or exceeds the best solution found, no later (larger) count can
+        // improve on it.
+        if let Some((best_notes, _)) = &best {
+            if count >= *best_notes {
+                break;
+            }
+        }
+
+        let used = tier.msats * count as u64;
+        if let Some(rest) =
+            select_coins_exact_search(tiers, suffix_value, idx + 1, remaining - used, memo)
+        {
+            let total_notes = count + rest.iter().sum::<usize>();
+            if best
+                .as_ref()
+                .map_or(true, |(notes, _)| total_notes < *notes)
+            {
+                let mut counts = Vec::with_capacity(rest.len() + 1);
+                counts.push(count);
+                counts.extend(rest);
+                best = Some((total_notes, counts));
+            }
+        }
+    }
+
+    let result = best.map(|(_, counts)| counts);
+    memo.insert((idx, remaining), result.clone());
+    result
 }
 
 impl TieredMulti<()> {
@@ -195,6 +415,92 @@ impl TieredMulti<()> {
     }
 }
 
+/// Error returned when parsing a [`TieredMulti::to_string`]-style representation fails
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum ParseTieredMultiError {
+    #[error("expected a comma-separated list of '<count>x<amount>msat' entries")]
+    InvalidSyntax,
+    #[error("invalid note count: {0}")]
+    InvalidCount(ParseIntError),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(ParseIntError),
+    #[error("note count at tier {0} is zero")]
+    ZeroCount(Amount),
+    #[error("tier {0} is listed more than once")]
+    DuplicateTier(Amount),
+    #[error("tier {0} is out of order, tiers must be sorted ascending")]
+    UnsortedTier(Amount),
+}
+
+/// Renders a canonical, sorted `<count>x<amount>msat,...` representation, e.g.
+/// `3x1msat,2x3000msat`.
+impl fmt::Display for TieredMulti<()> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tiers = self.0.iter().filter(|(_, notes)| !notes.is_empty());
+
+        if let Some((tier, notes)) = tiers.next() {
+            write!(f, "{}x{}msat", notes.len(), tier.msats)?;
+            for (tier, notes) in tiers {
+                write!(f, ",{}x{}msat", notes.len(), tier.msats)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the canonical representation produced by the `Display` impl. Rejects duplicate
+/// tiers, out-of-order tiers and zero counts rather than silently normalizing them, since a
+/// malformed string more likely indicates a bug than a user typo.
+impl FromStr for TieredMulti<()> {
+    type Err = ParseTieredMultiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Ok(TieredMulti(BTreeMap::new()));
+        }
+
+        let mut map = BTreeMap::new();
+        let mut last_tier: Option<Amount> = None;
+
+        for entry in s.split(',') {
+            if entry.is_empty() {
+                return Err(ParseTieredMultiError::InvalidSyntax);
+            }
+
+            let (count, amount) = entry
+                .split_once('x')
+                .ok_or(ParseTieredMultiError::InvalidSyntax)?;
+            let msats = amount
+                .strip_suffix("msat")
+                .ok_or(ParseTieredMultiError::InvalidSyntax)?;
+
+            let count: usize = count.parse().map_err(ParseTieredMultiError::InvalidCount)?;
+            let msats: u64 = msats
+                .parse()
+                .map_err(ParseTieredMultiError::InvalidAmount)?;
+            let tier = Amount { msats };
+
+            if count == 0 {
+                return Err(ParseTieredMultiError::ZeroCount(tier));
+            }
+            if let Some(last_tier) = last_tier {
+                if tier == last_tier {
+                    return Err(ParseTieredMultiError::DuplicateTier(tier));
+                }
+                if tier < last_tier {
+                    return Err(ParseTieredMultiError::UnsortedTier(tier));
+                }
+            }
+            last_tier = Some(tier);
+
+            map.insert(tier, vec![(); count]);
+        }
+
+        Ok(TieredMulti(map))
+    }
+}
+
 impl<C> FromIterator<(Amount, C)> for TieredMulti<C> {
     fn from_iter<T: IntoIterator<Item = (Amount, C)>>(iter: T) -> Self {
         let mut res = TieredMulti::default();
@@ -309,6 +615,58 @@ where
     }
 }
 
+/// Aligns the tiers of multiple [`TieredMulti`]s over the union of their tier sets: a tier
+/// missing from one peer yields `None` for that peer instead of panicking. Unlike
+/// [`TieredMultiZip`], this doesn't require callers to pre-check `structural_eq`, since
+/// federation peers' tier sets may legitimately differ (e.g. when aggregating blind-signature
+/// shares). Introduced as a separate type rather than changing `TieredMultiZip` in place,
+/// since that's a public constructor other workspace crates may already call.
+pub struct TieredMultiAlignZip<'a, T> {
+    iters: Vec<Peekable<btree_map::Iter<'a, Amount, Vec<T>>>>,
+}
+
+impl<'a, T> TieredMultiAlignZip<'a, T> {
+    /// Creates a new aligning iterator over the tiers of `multis`. There has to be at least
+    /// one `TieredMulti` in `multis`.
+    pub fn new(multis: Vec<&'a TieredMulti<T>>) -> Self {
+        assert!(!multis.is_empty());
+
+        TieredMultiAlignZip {
+            iters: multis
+                .into_iter()
+                .map(|multi| multi.0.iter().peekable())
+                .collect(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for TieredMultiAlignZip<'a, T> {
+    type Item = (Amount, Vec<Option<&'a Vec<T>>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tier = self
+            .iters
+            .iter_mut()
+            .filter_map(|iter| iter.peek().map(|(tier, _)| **tier))
+            .min()?;
+
+        let notes = self
+            .iters
+            .iter_mut()
+            .map(|iter| match iter.peek() {
+                Some((peer_tier, notes)) if **peer_tier == tier => {
+                    let notes = *notes;
+                    iter.next();
+                    Some(notes)
+                }
+                _ => None,
+            })
+            .collect();
+
+        Some((tier, notes))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use fedimint_api::Amount;
@@ -381,6 +739,232 @@ mod test {
         assert_eq!(starting.select_coins(Amount::from_sats(100)), None);
     }
 
+    #[test]
+    fn counts_by_tier_reports_note_count_per_tier() {
+        let starting = coins(vec![(Amount::from_sats(1), 3), (Amount::from_sats(2), 1)]);
+
+        assert_eq!(
+            starting.counts_by_tier(),
+            denominations(vec![(Amount::from_sats(1), 3), (Amount::from_sats(2), 1)])
+        );
+    }
+
+    #[test]
+    fn fold_tiers_sums_items_per_tier() {
+        let starting: TieredMulti<usize> = vec![
+            (Amount::from_sats(1), 1),
+            (Amount::from_sats(1), 2),
+            (Amount::from_sats(2), 5),
+        ]
+        .into_iter()
+        .collect();
+
+        let sums = starting.fold_tiers(|_| 0usize, |_, acc, item| acc + item);
+        assert_eq!(
+            sums,
+            denominations(vec![(Amount::from_sats(1), 3), (Amount::from_sats(2), 5)])
+        );
+    }
+
+    #[test]
+    fn min_and_max_item_by_tier_pick_extremes() {
+        let notes = vec![(Amount::from_sats(1), 3), (Amount::from_sats(1), 1)]
+            .into_iter()
+            .collect::<TieredMulti<usize>>();
+
+        assert_eq!(
+            notes.min_item_by_tier(),
+            vec![(Amount::from_sats(1), 1)].into_iter().collect()
+        );
+        assert_eq!(
+            notes.max_item_by_tier(),
+            vec![(Amount::from_sats(1), 3)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn select_coins_exact_finds_an_exact_subset() {
+        let starting = coins(vec![
+            (Amount::from_sats(1), 5),
+            (Amount::from_sats(5), 5),
+            (Amount::from_sats(20), 5),
+        ]);
+
+        assert_eq!(
+            starting.select_coins_exact(Amount::from_sats(11)),
+            Some(coins(vec![
+                (Amount::from_sats(1), 1),
+                (Amount::from_sats(5), 2)
+            ]))
+        );
+    }
+
+    #[test]
+    fn select_coins_exact_falls_back_when_no_exact_subset_exists() {
+        let starting = coins(vec![(Amount::from_sats(5), 1), (Amount::from_sats(20), 1)]);
+
+        // No subset of {5, 20} sums to 7, so this falls back to `select_coins`'s behavior.
+        assert_eq!(
+            starting.select_coins_exact(Amount::from_sats(7)),
+            starting.select_coins(Amount::from_sats(7))
+        );
+    }
+
+    #[test]
+    fn select_coins_exact_returns_none_if_amount_is_too_large() {
+        let starting = coins(vec![(Amount::from_sats(10), 1)]);
+
+        assert_eq!(starting.select_coins_exact(Amount::from_sats(100)), None);
+    }
+
+    #[test]
+    fn tiered_multi_align_zip_aligns_mismatched_tiers() {
+        let a = coins(vec![(Amount::from_sats(1), 1), (Amount::from_sats(2), 1)]);
+        let b = coins(vec![(Amount::from_sats(2), 1), (Amount::from_sats(3), 1)]);
+
+        let aligned: Vec<_> = super::TieredMultiAlignZip::new(vec![&a, &b])
+            .map(|(tier, notes)| {
+                (
+                    tier,
+                    notes.into_iter().map(|n| n.is_some()).collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            aligned,
+            vec![
+                (Amount::from_sats(1), vec![true, false]),
+                (Amount::from_sats(2), vec![true, true]),
+                (Amount::from_sats(3), vec![false, true]),
+            ]
+        );
+    }
+
+    #[test]
+    fn checked_total_amount_overflows_on_huge_counts() {
+        let huge = coins(vec![(Amount { msats: u64::MAX }, 2)]);
+        assert_eq!(huge.checked_total_amount(), Err(super::OverflowError));
+    }
+
+    #[test]
+    fn checked_add_merges_per_tier_counts() {
+        let a = coins(vec![(Amount::from_sats(1), 2), (Amount::from_sats(2), 1)]);
+        let b = coins(vec![(Amount::from_sats(1), 1), (Amount::from_sats(3), 1)]);
+
+        assert_eq!(
+            a.checked_add(&b),
+            Ok(coins(vec![
+                (Amount::from_sats(1), 3),
+                (Amount::from_sats(2), 1),
+                (Amount::from_sats(3), 1)
+            ]))
+        );
+    }
+
+    #[test]
+    fn checked_sub_prunes_emptied_tiers() {
+        let a = coins(vec![(Amount::from_sats(1), 2), (Amount::from_sats(2), 1)]);
+        let b = coins(vec![(Amount::from_sats(1), 2)]);
+
+        let result = a.checked_sub(&b).unwrap();
+        assert_eq!(result, coins(vec![(Amount::from_sats(2), 1)]));
+        assert_eq!(result.get(Amount::from_sats(1)), None);
+    }
+
+    #[test]
+    fn checked_sub_removes_notes_from_the_front_of_the_tier() {
+        let a: TieredMulti<usize> = vec![
+            (Amount::from_sats(1), 1),
+            (Amount::from_sats(1), 2),
+            (Amount::from_sats(1), 3),
+        ]
+        .into_iter()
+        .collect();
+        let b = coins(vec![(Amount::from_sats(1), 2)]);
+
+        let result = a.checked_sub(&b).unwrap();
+        assert_eq!(result.get(Amount::from_sats(1)), Some(&vec![3]));
+    }
+
+    #[test]
+    fn checked_sub_rejects_negative_counts() {
+        let a = coins(vec![(Amount::from_sats(1), 1)]);
+        let b = coins(vec![(Amount::from_sats(1), 2)]);
+
+        assert_eq!(
+            a.checked_sub(&b),
+            Err(super::TieredMultiSubError::InsufficientNotes(
+                Amount::from_sats(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn display_roundtrips_through_from_str() {
+        let counts = note_counts(vec![
+            (Amount::from_msats(1), 3),
+            (Amount::from_msats(3000), 2),
+        ]);
+
+        assert_eq!(counts.to_string(), "3x1msat,2x3000msat");
+        assert_eq!(counts.to_string().parse(), Ok(counts));
+    }
+
+    #[test]
+    fn from_str_rejects_duplicate_tiers() {
+        assert_eq!(
+            "1x1msat,1x1msat".parse::<TieredMulti<()>>(),
+            Err(super::ParseTieredMultiError::DuplicateTier(
+                Amount::from_msats(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn from_str_rejects_unsorted_tiers() {
+        assert_eq!(
+            "1x2msat,1x1msat".parse::<TieredMulti<()>>(),
+            Err(super::ParseTieredMultiError::UnsortedTier(
+                Amount::from_msats(1)
+            ))
+        );
+    }
+
+    #[test]
+    fn from_str_rejects_zero_counts() {
+        assert_eq!(
+            "0x1msat".parse::<TieredMulti<()>>(),
+            Err(super::ParseTieredMultiError::ZeroCount(Amount::from_msats(
+                1
+            )))
+        );
+    }
+
+    #[test]
+    fn from_str_parses_empty_string_as_empty() {
+        assert_eq!("".parse(), Ok(TieredMulti::<()>::default()));
+    }
+
+    #[test]
+    fn from_str_rejects_empty_segments() {
+        assert_eq!(
+            "3x1msat,,2x2msat".parse::<TieredMulti<()>>(),
+            Err(super::ParseTieredMultiError::InvalidSyntax)
+        );
+        assert_eq!(
+            "3x1msat,".parse::<TieredMulti<()>>(),
+            Err(super::ParseTieredMultiError::InvalidSyntax)
+        );
+    }
+
+    fn note_counts(counts: Vec<(Amount, usize)>) -> TieredMulti<()> {
+        counts
+            .into_iter()
+            .flat_map(|(amount, number)| vec![(amount, ()); number])
+            .collect()
+    }
+
     fn coins(coins: Vec<(Amount, usize)>) -> TieredMulti<usize> {
         coins
             .into_iter()